@@ -2,9 +2,9 @@
 //! This module provides a reader for the MNIST dataset.
 //! It downloads the dataset from GitHub if it is not already present in the specified directory.
 //! It also provides methods to load the data into memory.
-//! 
+//!
 //! You can easily download and use the MNIST data as shown below.
-//! 
+//!
 //! ```rust
 //! use mnist_reader::{MnistReader, print_image};
 //! fn main() {
@@ -20,20 +20,378 @@
 //!     // print the first image
 //!     let train_data: Vec<Vec<f32>> = mnist.train_data;
 //!     println!("images[0]={:?}", train_data[0]);
-//!     print_image(&train_data[0]);
+//!     print_image(&train_data[0], mnist.image_cols);
 //!     // print the first label
 //!     let train_labels: Vec<u8> = mnist.train_labels;
 //!     println!("labels[0]={:?}", train_labels[0]);
 //! }
 //! ```
-//! 
+//!
+//! If you need a validation split or want to customize the download
+//! behavior (file names, base URL, verbosity, forced re-downloads), build
+//! the reader with `MnistReaderBuilder` instead of `MnistReader::new`:
+//!
+//! ```rust
+//! use mnist_reader::MnistReaderBuilder;
+//! let mut mnist = MnistReaderBuilder::new("mnist-data")
+//!     .validation_len(5000)
+//!     .verbose(false)
+//!     .build();
+//! mnist.load().unwrap();
+//! println!("Validation data size: {}", mnist.val_data.len());
+//! ```
+//!
+//! With the `ndarray` feature enabled, the loaded data can also be pulled
+//! out as contiguous `ndarray` arrays instead of `Vec<Vec<f32>>`, and
+//! labels can be one-hot encoded:
+//!
+//! ```rust,ignore
+//! let images = mnist.train_images_array();   // Array2<f32>, shape [n, image_rows * image_cols]
+//! let images3 = mnist.train_images_array3(); // Array3<f32>, shape [n, image_rows, image_cols]
+//! let onehot = mnist.train_labels_onehot();  // Array2<f32>, shape [n, num_classes]
+//! ```
+//!
+//! For training loops, `DataLoader` iterates a split in shuffled
+//! mini-batches instead of slicing the vectors by hand:
+//!
+//! ```rust,ignore
+//! for (images, labels) in mnist.train_loader(32).shuffle(true).seed(42) {
+//!     // images: Vec<Vec<f32>>, labels: Vec<u8>
+//! }
+//! ```
+//!
+//! Other IDX-format datasets (Fashion-MNIST, Kuzushiji-MNIST, EMNIST, or
+//! anything distributed as four gzipped IDX files) can be loaded through
+//! the same API by selecting a `Dataset`:
+//!
+//! ```rust,ignore
+//! use mnist_reader::{Dataset, MnistReaderBuilder};
+//! let mut fashion = MnistReaderBuilder::new("fashion-data")
+//!     .dataset(Dataset::FashionMnist)
+//!     .build();
+//! fashion.load().unwrap();
+//! ```
+//!
+//! The loaded data can also be exported as a `.tfrecord` file for
+//! TensorFlow-based pipelines:
+//!
+//! ```rust,ignore
+//! use mnist_reader::Split;
+//! mnist.write_tfrecord("train.tfrecord", Split::Train).unwrap();
+//! ```
+//!
+//! By default pixels are scaled into `[0.0, 1.0]`. Standardization,
+//! binarization, a raw `[0.0, 255.0]` pass-through, or an arbitrary
+//! per-image closure can be configured on the builder instead:
+//!
+//! ```rust,ignore
+//! let mut mnist = MnistReaderBuilder::new("mnist-data")
+//!     .normalize(0.1307, 0.3081)
+//!     .build();
+//! ```
+//!
 use std::fs::{self, File};
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use flate2::read::GzDecoder;
 use ureq;
 use std::path::Path;
+use std::rc::Rc;
 
 static MNIST_DATA_URL: &str = "https://raw.githubusercontent.com/fgnt/mnist/master";
+static DEFAULT_TRAIN_IMAGES_FILE: &str = "train-images-idx3-ubyte.gz";
+static DEFAULT_TRAIN_LABELS_FILE: &str = "train-labels-idx1-ubyte.gz";
+static DEFAULT_TEST_IMAGES_FILE: &str = "t10k-images-idx3-ubyte.gz";
+static DEFAULT_TEST_LABELS_FILE: &str = "t10k-labels-idx1-ubyte.gz";
+
+/// File names and base URL for an MNIST-format dataset (four gzipped IDX
+/// files: train/test images and labels).
+#[derive(Debug, Clone)]
+pub struct DatasetSource {
+    pub mnist_url: String,
+    pub train_images_file: String,
+    pub train_labels_file: String,
+    pub test_images_file: String,
+    pub test_labels_file: String,
+}
+
+/// an arbitrary per-image pixel transform, shared via `Rc` so `PixelTransform` stays `Clone`
+type PixelTransformFn = Rc<dyn Fn(&mut [f32])>;
+
+/// Per-image pixel transform applied once during `load()`, replacing the
+/// hardcoded `byte / 255.0` normalization. `Standardize` and `Binarize`
+/// are applied after the base `[0.0, 1.0]` scaling; `Raw` skips that
+/// scaling so callers can normalize the bytes themselves.
+pub enum PixelTransform {
+    /// scale each byte into `[0.0, 1.0]` (the default)
+    Normalize01,
+    /// keep raw pixel values in `[0.0, 255.0]`, skipping the divide
+    Raw,
+    /// mean/std standardization: `(pixel - mean) / std`
+    Standardize { mean: f32, std: f32 },
+    /// threshold each (already `[0.0, 1.0]`-scaled) pixel to 0.0 or 1.0
+    Binarize { threshold: f32 },
+    /// an arbitrary transform run over each image's pixels in place
+    Custom(PixelTransformFn),
+}
+impl Clone for PixelTransform {
+    fn clone(&self) -> Self {
+        match self {
+            PixelTransform::Normalize01 => PixelTransform::Normalize01,
+            PixelTransform::Raw => PixelTransform::Raw,
+            PixelTransform::Standardize { mean, std } => {
+                PixelTransform::Standardize { mean: *mean, std: *std }
+            }
+            PixelTransform::Binarize { threshold } => PixelTransform::Binarize { threshold: *threshold },
+            PixelTransform::Custom(transform) => PixelTransform::Custom(Rc::clone(transform)),
+        }
+    }
+}
+impl std::fmt::Debug for PixelTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PixelTransform::Normalize01 => write!(f, "Normalize01"),
+            PixelTransform::Raw => write!(f, "Raw"),
+            PixelTransform::Standardize { mean, std } => {
+                write!(f, "Standardize {{ mean: {}, std: {} }}", mean, std)
+            }
+            PixelTransform::Binarize { threshold } => write!(f, "Binarize {{ threshold: {} }}", threshold),
+            PixelTransform::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// Selects which loaded data split to operate on, e.g. for `write_tfrecord`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    Train,
+    Validation,
+    Test,
+}
+
+/// Selects which MNIST-format dataset to download and parse. All variants
+/// share the same IDX parsing path; only the source URL and file names
+/// differ. Use `Custom` to point at any other IDX-format dataset.
+#[derive(Debug, Clone)]
+pub enum Dataset {
+    /// the classic handwritten digit dataset
+    Mnist,
+    /// Zalando's Fashion-MNIST clothing dataset
+    FashionMnist,
+    /// Kuzushiji-MNIST, cursive Japanese character dataset
+    KuzushijiMnist,
+    /// EMNIST (balanced split), extended handwritten character dataset
+    Emnist,
+    /// any other dataset distributed as four gzipped IDX files
+    Custom(DatasetSource),
+}
+impl Dataset {
+    /// resolve this selection to a concrete `DatasetSource`
+    fn source(&self) -> DatasetSource {
+        match self {
+            Dataset::Mnist => DatasetSource {
+                mnist_url: MNIST_DATA_URL.to_string(),
+                train_images_file: DEFAULT_TRAIN_IMAGES_FILE.to_string(),
+                train_labels_file: DEFAULT_TRAIN_LABELS_FILE.to_string(),
+                test_images_file: DEFAULT_TEST_IMAGES_FILE.to_string(),
+                test_labels_file: DEFAULT_TEST_LABELS_FILE.to_string(),
+            },
+            Dataset::FashionMnist => DatasetSource {
+                mnist_url: "https://raw.githubusercontent.com/zalandoresearch/fashion-mnist/master/data/fashion".to_string(),
+                train_images_file: "train-images-idx3-ubyte.gz".to_string(),
+                train_labels_file: "train-labels-idx1-ubyte.gz".to_string(),
+                test_images_file: "t10k-images-idx3-ubyte.gz".to_string(),
+                test_labels_file: "t10k-labels-idx1-ubyte.gz".to_string(),
+            },
+            Dataset::KuzushijiMnist => DatasetSource {
+                mnist_url: "http://codh.rois.ac.jp/kmnist/dataset/kmnist".to_string(),
+                train_images_file: "train-images-idx3-ubyte.gz".to_string(),
+                train_labels_file: "train-labels-idx1-ubyte.gz".to_string(),
+                test_images_file: "t10k-images-idx3-ubyte.gz".to_string(),
+                test_labels_file: "t10k-labels-idx1-ubyte.gz".to_string(),
+            },
+            Dataset::Emnist => DatasetSource {
+                mnist_url: "https://biometrics.nist.gov/cs_links/EMNIST/gzip".to_string(),
+                train_images_file: "emnist-balanced-train-images-idx3-ubyte.gz".to_string(),
+                train_labels_file: "emnist-balanced-train-labels-idx1-ubyte.gz".to_string(),
+                test_images_file: "emnist-balanced-test-images-idx3-ubyte.gz".to_string(),
+                test_labels_file: "emnist-balanced-test-labels-idx1-ubyte.gz".to_string(),
+            },
+            Dataset::Custom(source) => source.clone(),
+        }
+    }
+    /// the number of label classes this dataset ships with, used as the
+    /// default one-hot width. `Custom` datasets default to 10 (the most
+    /// common case); override with `MnistReaderBuilder::num_classes` if
+    /// your custom dataset has a different label range.
+    fn num_classes(&self) -> usize {
+        match self {
+            Dataset::Mnist | Dataset::FashionMnist | Dataset::KuzushijiMnist => 10,
+            Dataset::Emnist => 47,
+            Dataset::Custom(_) => 10,
+        }
+    }
+}
+
+/// Builder for `MnistReader`.
+/// Lets callers override the download source, file names, verbosity and
+/// forced re-download behavior, and carve a validation set out of the
+/// tail of the training set before calling `build()`.
+#[derive(Debug, Clone)]
+pub struct MnistReaderBuilder {
+    save_dir: String,
+    mnist_url: String,
+    train_images_file: String,
+    train_labels_file: String,
+    test_images_file: String,
+    test_labels_file: String,
+    force_download: bool,
+    verbose: bool,
+    validation_len: usize,
+    validation_fraction: Option<f32>,
+    pixel_transform: PixelTransform,
+    num_classes: usize,
+}
+impl MnistReaderBuilder {
+    /// create a new builder with the classic MNIST defaults
+    pub fn new(save_dir: &str) -> Self {
+        MnistReaderBuilder {
+            save_dir: save_dir.to_string(),
+            mnist_url: MNIST_DATA_URL.to_string(),
+            train_images_file: DEFAULT_TRAIN_IMAGES_FILE.to_string(),
+            train_labels_file: DEFAULT_TRAIN_LABELS_FILE.to_string(),
+            test_images_file: DEFAULT_TEST_IMAGES_FILE.to_string(),
+            test_labels_file: DEFAULT_TEST_LABELS_FILE.to_string(),
+            force_download: false,
+            verbose: true,
+            validation_len: 0,
+            validation_fraction: None,
+            pixel_transform: PixelTransform::Normalize01,
+            num_classes: Dataset::Mnist.num_classes(),
+        }
+    }
+    /// select a built-in dataset variant (or a fully custom IDX source),
+    /// setting the base URL, the four file names, and the default
+    /// one-hot class count in one call. Call this before any of the
+    /// individual file-name/URL/`num_classes` overrides if you want
+    /// those overrides to take precedence.
+    pub fn dataset(mut self, dataset: Dataset) -> Self {
+        self.num_classes = dataset.num_classes();
+        let source = dataset.source();
+        self.mnist_url = source.mnist_url;
+        self.train_images_file = source.train_images_file;
+        self.train_labels_file = source.train_labels_file;
+        self.test_images_file = source.test_images_file;
+        self.test_labels_file = source.test_labels_file;
+        self
+    }
+    /// override the base URL the dataset files are downloaded from
+    pub fn mnist_url(mut self, mnist_url: &str) -> Self {
+        self.mnist_url = mnist_url.to_string();
+        self
+    }
+    /// override the train images file name
+    pub fn train_images_file(mut self, file_name: &str) -> Self {
+        self.train_images_file = file_name.to_string();
+        self
+    }
+    /// override the train labels file name
+    pub fn train_labels_file(mut self, file_name: &str) -> Self {
+        self.train_labels_file = file_name.to_string();
+        self
+    }
+    /// override the test images file name
+    pub fn test_images_file(mut self, file_name: &str) -> Self {
+        self.test_images_file = file_name.to_string();
+        self
+    }
+    /// override the test labels file name
+    pub fn test_labels_file(mut self, file_name: &str) -> Self {
+        self.test_labels_file = file_name.to_string();
+        self
+    }
+    /// force re-download even if the files already exist in `save_dir`
+    pub fn force_download(mut self, force_download: bool) -> Self {
+        self.force_download = force_download;
+        self
+    }
+    /// enable or disable progress messages while downloading/loading
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+    /// carve out `validation_len` examples from the tail of the training
+    /// set to populate `val_data`/`val_labels`. A non-zero `validation_len`
+    /// always takes precedence over `validation_fraction`, regardless of
+    /// which setter was called last; pass `0` to defer to
+    /// `validation_fraction` instead.
+    pub fn validation_len(mut self, validation_len: usize) -> Self {
+        self.validation_len = validation_len;
+        self
+    }
+    /// carve out a validation set sized as a fraction of the training set,
+    /// e.g. `0.1` reserves the last 10% of training examples. Only used
+    /// when `validation_len` is `0` (its default).
+    pub fn validation_fraction(mut self, fraction: f32) -> Self {
+        self.validation_fraction = Some(fraction);
+        self
+    }
+    /// set the pixel transform applied per image at load time (default:
+    /// scale each byte into `[0.0, 1.0]`)
+    pub fn pixel_transform(mut self, pixel_transform: PixelTransform) -> Self {
+        self.pixel_transform = pixel_transform;
+        self
+    }
+    /// keep raw `[0.0, 255.0]` pixel values, skipping the `/255` divide,
+    /// for callers that want to normalize the bytes themselves
+    pub fn raw_pixels(self) -> Self {
+        self.pixel_transform(PixelTransform::Raw)
+    }
+    /// mean/std standardize pixels (applied after the `[0.0, 1.0]` scaling)
+    pub fn normalize(self, mean: f32, std: f32) -> Self {
+        self.pixel_transform(PixelTransform::Standardize { mean, std })
+    }
+    /// threshold pixels (after `[0.0, 1.0]` scaling) to 0.0 or 1.0
+    pub fn binarize(self, threshold: f32) -> Self {
+        self.pixel_transform(PixelTransform::Binarize { threshold })
+    }
+    /// run an arbitrary transform over each image's pixels in place
+    pub fn custom_transform(self, transform: impl Fn(&mut [f32]) + 'static) -> Self {
+        self.pixel_transform(PixelTransform::Custom(Rc::new(transform)))
+    }
+    /// override the number of label classes used to size one-hot encodings
+    /// (see `MnistReader::train_labels_onehot` et al.). Defaults to the
+    /// selected `Dataset`'s class count (10 for MNIST-like datasets, 47
+    /// for EMNIST-balanced).
+    pub fn num_classes(mut self, num_classes: usize) -> Self {
+        self.num_classes = num_classes;
+        self
+    }
+    /// build the configured `MnistReader`
+    pub fn build(self) -> MnistReader {
+        MnistReader {
+            train_labels: Vec::new(),
+            train_data: Vec::new(),
+            val_labels: Vec::new(),
+            val_data: Vec::new(),
+            test_labels: Vec::new(),
+            test_data: Vec::new(),
+            mnist_url: self.mnist_url,
+            save_dir: self.save_dir,
+            train_images_file: self.train_images_file,
+            train_labels_file: self.train_labels_file,
+            test_images_file: self.test_images_file,
+            test_labels_file: self.test_labels_file,
+            force_download: self.force_download,
+            verbose: self.verbose,
+            validation_len: self.validation_len,
+            validation_fraction: self.validation_fraction,
+            pixel_transform: self.pixel_transform,
+            num_classes: self.num_classes,
+            image_rows: 0,
+            image_cols: 0,
+        }
+    }
+}
 
 /// MNIST data reader
 /// This struct is used to read MNIST data from the given directory.
@@ -43,41 +401,62 @@ static MNIST_DATA_URL: &str = "https://raw.githubusercontent.com/fgnt/mnist/mast
 pub struct MnistReader {
     pub train_labels: Vec<u8>,
     pub train_data: Vec<Vec<f32>>,
+    pub val_labels: Vec<u8>,
+    pub val_data: Vec<Vec<f32>>,
     pub test_labels: Vec<u8>,
     pub test_data: Vec<Vec<f32>>,
     pub mnist_url: String,
     pub save_dir: String,
+    /// row count of each image, read from the IDX header after `load()`
+    pub image_rows: usize,
+    /// column count of each image, read from the IDX header after `load()`
+    pub image_cols: usize,
+    /// number of label classes used to size one-hot encodings (see
+    /// `MnistReaderBuilder::num_classes`)
+    pub num_classes: usize,
+    train_images_file: String,
+    train_labels_file: String,
+    test_images_file: String,
+    test_labels_file: String,
+    force_download: bool,
+    verbose: bool,
+    validation_len: usize,
+    validation_fraction: Option<f32>,
+    pixel_transform: PixelTransform,
 }
 impl MnistReader {
-    /// create a new MnistReader
+    /// create a new MnistReader with the classic MNIST defaults and no
+    /// validation split. Use `MnistReaderBuilder` for more control.
     pub fn new(save_dir: &str) -> Self {
-        MnistReader {
-            train_labels: Vec::new(),
-            train_data: Vec::new(),
-            test_labels: Vec::new(),
-            test_data: Vec::new(),
-            mnist_url: MNIST_DATA_URL.to_string(),
-            save_dir: save_dir.to_string(),
-        }
+        MnistReaderBuilder::new(save_dir).build()
     }
     /// download MNIST data files
     pub fn download_files(save_dir: &str, mnist_url: &str) -> io::Result<()> {
+        MnistReaderBuilder::new(save_dir)
+            .mnist_url(mnist_url)
+            .build()
+            .download_configured_files()
+    }
+    /// download the dataset files configured on this reader
+    fn download_configured_files(&self) -> io::Result<()> {
         // check directory
-        fs::create_dir_all(save_dir)?;
+        fs::create_dir_all(&self.save_dir)?;
         // download files
         let files = [
-            "train-images-idx3-ubyte.gz",
-            "train-labels-idx1-ubyte.gz",
-            "t10k-images-idx3-ubyte.gz",
-            "t10k-labels-idx1-ubyte.gz",
+            &self.train_images_file,
+            &self.train_labels_file,
+            &self.test_images_file,
+            &self.test_labels_file,
         ];
         for file in &files {
-            let url = format!("{}/{}", mnist_url, file);
-            let out_path = format!("{}/{}", save_dir, file);
-            if !Path::new(&out_path).exists() {
-                println!("Downloading: {}...", file);
+            let url = format!("{}/{}", self.mnist_url, file);
+            let out_path = format!("{}/{}", self.save_dir, file);
+            if self.force_download || !Path::new(&out_path).exists() {
+                if self.verbose {
+                    println!("Downloading: {}...", file);
+                }
                 download_file(&url, &out_path)?;
-            } else {
+            } else if self.verbose {
                 println!("File: {}", file);
             }
         }
@@ -86,20 +465,36 @@ impl MnistReader {
     /// load all MNIST data
     pub fn load(&mut self) -> io::Result<()> {
         // check directory
-        Self::download_files(&self.save_dir, &self.mnist_url)?;
+        self.download_configured_files()?;
         // load train data
         self.load_data(true)?;
         self.load_data(false)?;
+        // carve out a validation split from the tail of the training set
+        self.split_validation();
         Ok(())
 
     }
     /// load MNIST data
     fn load_data(&mut self, is_train: bool) -> io::Result<()> {
-        let type_str = if is_train { "train" } else { "t10k" };
-        let label_file = format!("{}/{}-labels-idx1-ubyte.gz", self.save_dir, type_str);
-        let image_file = format!("{}/{}-images-idx3-ubyte.gz", self.save_dir, type_str);
-        let labels = read_mnist_labels(&label_file).unwrap();
-        let images = read_mnist_images(&image_file).unwrap();
+        let (label_file, image_file) = if is_train {
+            (&self.train_labels_file, &self.train_images_file)
+        } else {
+            (&self.test_labels_file, &self.test_images_file)
+        };
+        let label_path = format!("{}/{}", self.save_dir, label_file);
+        let image_path = format!("{}/{}", self.save_dir, image_file);
+        let labels = read_mnist_labels(&label_path)?;
+        let (images, image_rows, image_cols) = read_mnist_images(&image_path, &self.pixel_transform)?;
+        if images.len() != labels.len() {
+            return Err(invalid_data(&format!(
+                "image/label count mismatch in {}: {} images vs {} labels",
+                if is_train { "train" } else { "test" },
+                images.len(),
+                labels.len()
+            )));
+        }
+        self.image_rows = image_rows;
+        self.image_cols = image_cols;
         if is_train {
             self.train_labels = labels;
             self.train_data = images;
@@ -109,7 +504,161 @@ impl MnistReader {
         }
         Ok(())
     }
+    /// move the configured number of examples from the tail of the
+    /// training set into `val_data`/`val_labels`
+    fn split_validation(&mut self) {
+        let val_len = if self.validation_len > 0 {
+            self.validation_len
+        } else {
+            match self.validation_fraction {
+                Some(fraction) => ((self.train_data.len() as f32) * fraction) as usize,
+                None => 0,
+            }
+        };
+        let val_len = val_len.min(self.train_data.len());
+        if val_len == 0 {
+            return;
+        }
+        let split_at = self.train_data.len() - val_len;
+        self.val_data = self.train_data.split_off(split_at);
+        self.val_labels = self.train_labels.split_off(split_at);
+    }
+    /// mini-batch loader over the training split
+    pub fn train_loader(&self, batch_size: usize) -> DataLoader<'_> {
+        DataLoader::new(&self.train_data, &self.train_labels, batch_size)
+    }
+    /// mini-batch loader over the validation split
+    pub fn val_loader(&self, batch_size: usize) -> DataLoader<'_> {
+        DataLoader::new(&self.val_data, &self.val_labels, batch_size)
+    }
+    /// mini-batch loader over the test split
+    pub fn test_loader(&self, batch_size: usize) -> DataLoader<'_> {
+        DataLoader::new(&self.test_data, &self.test_labels, batch_size)
+    }
+    /// export `split` to a TFRecord file: one record per example, each
+    /// holding an `image` float feature (the pixels as loaded, in whatever
+    /// scale the configured `PixelTransform` produced — `[0.0, 1.0]` by
+    /// default, but raw, standardized, or custom-transformed if
+    /// configured) and a `label` int64 feature
+    pub fn write_tfrecord(&self, path: &str, split: Split) -> io::Result<()> {
+        let (images, labels) = match split {
+            Split::Train => (&self.train_data, &self.train_labels),
+            Split::Validation => (&self.val_data, &self.val_labels),
+            Split::Test => (&self.test_data, &self.test_labels),
+        };
+        let mut file = File::create(path)?;
+        for (image, &label) in images.iter().zip(labels.iter()) {
+            let example = encode_tfrecord_example(image, label);
+            write_tfrecord_entry(&mut file, &example)?;
+        }
+        Ok(())
+    }
+
+}
 
+/// a small seeded PRNG (xorshift64) used to shuffle batch order without
+/// pulling in an external RNG dependency
+struct Xorshift64 {
+    state: u64,
+}
+impl Xorshift64 {
+    fn new(seed: u64) -> Self {
+        // xorshift64 is undefined for a zero state
+        Xorshift64 { state: if seed == 0 { 0x9E3779B97F4A7C15 } else { seed } }
+    }
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+    /// a random index in `[0, bound)`
+    fn next_below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Iterates a data split in mini-batches, with optional per-epoch
+/// shuffling. Borrows the images/labels from an `MnistReader` split (see
+/// `MnistReader::train_loader`/`val_loader`/`test_loader`) so no data is
+/// copied until a batch is produced.
+pub struct DataLoader<'a> {
+    data: &'a [Vec<f32>],
+    labels: &'a [u8],
+    batch_size: usize,
+    shuffle: bool,
+    seed: Option<u64>,
+    drop_last: bool,
+    indices: Vec<usize>,
+    cursor: usize,
+    shuffled: bool,
+}
+impl<'a> DataLoader<'a> {
+    /// create a loader over `data`/`labels` yielding batches of `batch_size`.
+    /// panics if `batch_size` is `0`, since that would never advance and
+    /// iterate forever.
+    pub fn new(data: &'a [Vec<f32>], labels: &'a [u8], batch_size: usize) -> Self {
+        assert!(batch_size > 0, "DataLoader batch_size must be greater than 0");
+        DataLoader {
+            data,
+            labels,
+            batch_size,
+            shuffle: false,
+            seed: None,
+            drop_last: false,
+            indices: (0..data.len()).collect(),
+            cursor: 0,
+            shuffled: false,
+        }
+    }
+    /// shuffle example order once per epoch
+    pub fn shuffle(mut self, shuffle: bool) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+    /// seed the shuffle RNG for reproducible batch order
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    /// drop the final partial batch instead of yielding it
+    pub fn drop_last(mut self, drop_last: bool) -> Self {
+        self.drop_last = drop_last;
+        self
+    }
+    /// permute example indices with a Fisher-Yates shuffle
+    fn reshuffle(&mut self) {
+        let seed = self.seed.unwrap_or(0x2545F4914F6CDD1D);
+        let mut rng = Xorshift64::new(seed);
+        for i in (1..self.indices.len()).rev() {
+            let j = rng.next_below(i + 1);
+            self.indices.swap(i, j);
+        }
+    }
+}
+impl<'a> Iterator for DataLoader<'a> {
+    type Item = (Vec<Vec<f32>>, Vec<u8>);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.shuffle && !self.shuffled {
+            self.reshuffle();
+            self.shuffled = true;
+        }
+        if self.cursor >= self.indices.len() {
+            return None;
+        }
+        let end = (self.cursor + self.batch_size).min(self.indices.len());
+        let batch_indices = &self.indices[self.cursor..end];
+        if self.drop_last && batch_indices.len() < self.batch_size {
+            self.cursor = self.indices.len();
+            return None;
+        }
+        let images = batch_indices.iter().map(|&i| self.data[i].clone()).collect();
+        let labels = batch_indices.iter().map(|&i| self.labels[i]).collect();
+        self.cursor = end;
+        Some((images, labels))
+    }
 }
 
 /// download a file from url
@@ -125,6 +674,98 @@ fn download_file(url: &str, out_path: &str) -> io::Result<()> {
 }
 
 
+/// CRC32C (Castagnoli) checksum, as used by the TFRecord framing format
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F63B78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// the masked CRC32C used by TFRecord, per the reference implementation:
+/// rotate the CRC by 15 bits and add a fixed constant so that it doesn't
+/// alias with a checksum of the record's own checksum bytes
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+    crc.rotate_right(15).wrapping_add(0xa282ead8)
+}
+
+/// write one length-prefixed, CRC32C-masked TFRecord entry
+fn write_tfrecord_entry(writer: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let length_bytes = (data.len() as u64).to_le_bytes();
+    writer.write_all(&length_bytes)?;
+    writer.write_all(&masked_crc32c(&length_bytes).to_le_bytes())?;
+    writer.write_all(data)?;
+    writer.write_all(&masked_crc32c(data).to_le_bytes())?;
+    Ok(())
+}
+
+/// encode a protobuf varint
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            break;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+/// encode a protobuf length-delimited field (strings, bytes, embedded messages)
+fn write_length_delimited(buf: &mut Vec<u8>, field_number: u32, data: &[u8]) {
+    write_varint(buf, ((field_number as u64) << 3) | 2); // wire type 2: length-delimited
+    write_varint(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+/// encode one example as a serialized `tensorflow.Example` protobuf message:
+/// a `Features` map with an `image` float feature and a `label` int64 feature
+fn encode_tfrecord_example(image: &[f32], label: u8) -> Vec<u8> {
+    let mut packed_floats = Vec::with_capacity(image.len() * 4);
+    for &pixel in image {
+        packed_floats.extend_from_slice(&pixel.to_le_bytes());
+    }
+    let mut float_list = Vec::new();
+    write_length_delimited(&mut float_list, 1, &packed_floats); // FloatList.value
+
+    let mut packed_label = Vec::new();
+    write_varint(&mut packed_label, label as u64);
+    let mut int64_list = Vec::new();
+    write_length_delimited(&mut int64_list, 1, &packed_label); // Int64List.value
+
+    let mut image_feature = Vec::new();
+    write_length_delimited(&mut image_feature, 2, &float_list); // Feature.float_list
+
+    let mut label_feature = Vec::new();
+    write_length_delimited(&mut label_feature, 3, &int64_list); // Feature.int64_list
+
+    let mut image_entry = Vec::new();
+    write_length_delimited(&mut image_entry, 1, b"image"); // MapEntry.key
+    write_length_delimited(&mut image_entry, 2, &image_feature); // MapEntry.value
+
+    let mut label_entry = Vec::new();
+    write_length_delimited(&mut label_entry, 1, b"label");
+    write_length_delimited(&mut label_entry, 2, &label_feature);
+
+    let mut features = Vec::new();
+    write_length_delimited(&mut features, 1, &image_entry); // Features.feature[0]
+    write_length_delimited(&mut features, 1, &label_entry); // Features.feature[1]
+
+    let mut example = Vec::new();
+    write_length_delimited(&mut example, 1, &features); // Example.features
+    example
+}
+
 /// ungzip a file
 pub fn ungzip(in_path: &str, out_path: &str) -> io::Result<()> {
     let input = File::open(in_path)?;
@@ -144,42 +785,122 @@ pub fn read_gzip(in_path: &str) -> io::Result<Vec<u8>> {
 }
 
 
-/// read MNIST labels
+const LABEL_MAGIC: u32 = 2049;
+const IMAGE_MAGIC: u32 = 2051;
+
+/// read a big-endian u32 at `offset`, erroring if the buffer is too short
+fn read_be_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or_else(|| invalid_data(&format!("buffer too short to read header at offset {}", offset)))
+}
+
+/// build an `io::Error` of kind `InvalidData` with the given message
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// read MNIST labels, validating the IDX header before trusting the bytes
 fn read_mnist_labels(file_path: &str) -> io::Result<Vec<u8>> {
     let data = read_gzip(file_path)?;
-    // skip 8 bytes of header
-    let labels = data[8..].to_vec();
-    Ok(labels)
+    if data.len() < 8 {
+        return Err(invalid_data("label file is shorter than the IDX header"));
+    }
+    let magic = read_be_u32(&data, 0)?;
+    if magic != LABEL_MAGIC {
+        return Err(invalid_data(&format!(
+            "unexpected label file magic number: expected {}, got {}",
+            LABEL_MAGIC, magic
+        )));
+    }
+    let num_labels = read_be_u32(&data, 4)? as usize;
+    let labels = &data[8..];
+    if labels.len() != num_labels {
+        return Err(invalid_data(&format!(
+            "label count mismatch: header declares {} labels, but {} bytes follow the header",
+            num_labels,
+            labels.len()
+        )));
+    }
+    Ok(labels.to_vec())
+}
+
+/// scale a raw pixel byte according to the configured base transform
+fn base_pixel_value(byte: u8, transform: &PixelTransform) -> f32 {
+    match transform {
+        PixelTransform::Raw => byte as f32,
+        _ => byte as f32 / 255.0,
+    }
 }
 
-/// read MNIST images
-fn read_mnist_images(file_path: &str) -> io::Result<Vec<Vec<f32>>> {
+/// apply the configured transform's per-image pass, in place
+fn apply_pixel_transform(image: &mut [f32], transform: &PixelTransform) {
+    match transform {
+        PixelTransform::Normalize01 | PixelTransform::Raw => {}
+        PixelTransform::Standardize { mean, std } => {
+            for pixel in image.iter_mut() {
+                *pixel = (*pixel - mean) / std;
+            }
+        }
+        PixelTransform::Binarize { threshold } => {
+            for pixel in image.iter_mut() {
+                *pixel = if *pixel > *threshold { 1.0 } else { 0.0 };
+            }
+        }
+        PixelTransform::Custom(transform_fn) => transform_fn(image),
+    }
+}
+
+/// read MNIST images, validating the IDX header before trusting the bytes.
+/// returns the images alongside the row/column counts declared in the header.
+fn read_mnist_images(file_path: &str, transform: &PixelTransform) -> io::Result<(Vec<Vec<f32>>, usize, usize)> {
     let raw_bytes = read_gzip(file_path)?;
+    if raw_bytes.len() < 16 {
+        return Err(invalid_data("image file is shorter than the IDX header"));
+    }
 
     // read header
-    let num_images = u32::from_be_bytes(raw_bytes[4..8].try_into().unwrap()) as usize;
-    let num_rows = u32::from_be_bytes(raw_bytes[8..12].try_into().unwrap()) as usize;
-    let num_cols = u32::from_be_bytes(raw_bytes[12..16].try_into().unwrap()) as usize;
+    let magic = read_be_u32(&raw_bytes, 0)?;
+    if magic != IMAGE_MAGIC {
+        return Err(invalid_data(&format!(
+            "unexpected image file magic number: expected {}, got {}",
+            IMAGE_MAGIC, magic
+        )));
+    }
+    let num_images = read_be_u32(&raw_bytes, 4)? as usize;
+    let num_rows = read_be_u32(&raw_bytes, 8)? as usize;
+    let num_cols = read_be_u32(&raw_bytes, 12)? as usize;
     let image_size = num_rows * num_cols;
 
+    let expected_len = image_size * num_images + 16;
+    if raw_bytes.len() != expected_len {
+        return Err(invalid_data(&format!(
+            "image data length mismatch: expected {} bytes ({} images of {}x{}, plus header), got {}",
+            expected_len, num_images, num_rows, num_cols, raw_bytes.len()
+        )));
+    }
+
     let mut images = Vec::with_capacity(num_images);
     let images_raw = &raw_bytes[16..]; // header is 16 bytes
 
     for i in 0..num_images {
         let start = i * image_size;
         let end = start + image_size;
-        let image: Vec<f32> = images_raw[start..end]
+        let mut image: Vec<f32> = images_raw[start..end]
             .iter()
-            .map(|&b| b as f32 / 255.0)
+            .map(|&b| base_pixel_value(b, transform))
             .collect();
+        apply_pixel_transform(&mut image, transform);
         images.push(image);
     }
-    Ok(images)
+    Ok((images, num_rows, num_cols))
 }
 
-/// print MNIST image data
-pub fn print_image(image: &[f32]) {
-    for row in image.chunks(28) {
+/// print MNIST image data. `row_width` is the number of pixels per row
+/// (28 for classic MNIST-format datasets, `reader.image_cols` in general).
+pub fn print_image(image: &[f32], row_width: usize) {
+    for row in image.chunks(row_width) {
         for &pixel in row {
             if pixel > 0.5 {
                 print!("*");
@@ -192,9 +913,153 @@ pub fn print_image(image: &[f32]) {
 }
 
 
+/// ndarray-typed accessors for the loaded data, and one-hot label encoding
+#[cfg(feature = "ndarray")]
+mod ndarray_support {
+    use super::MnistReader;
+    use ndarray::{Array2, Array3};
+
+    /// flatten a slice of equal-length image vectors into a contiguous `Array2<f32>`
+    fn images_to_array2(data: &[Vec<f32>]) -> Array2<f32> {
+        let n = data.len();
+        let pixels = data.first().map_or(0, |image| image.len());
+        let flat: Vec<f32> = data.iter().flatten().copied().collect();
+        Array2::from_shape_vec((n, pixels), flat).expect("image rows must all be the same length")
+    }
+
+    /// one-hot encode a slice of digit labels into an `Array2<f32>` of shape `[n, num_classes]`
+    fn labels_to_onehot(labels: &[u8], num_classes: usize) -> Array2<f32> {
+        let mut onehot = Array2::<f32>::zeros((labels.len(), num_classes));
+        for (row, &label) in labels.iter().enumerate() {
+            assert!(
+                (label as usize) < num_classes,
+                "label {} is out of range for num_classes={} (set MnistReaderBuilder::num_classes to match your dataset)",
+                label, num_classes
+            );
+            onehot[[row, label as usize]] = 1.0;
+        }
+        onehot
+    }
+
+    impl MnistReader {
+        /// training images as a contiguous `Array2<f32>` of shape `[n, image_rows * image_cols]`
+        pub fn train_images_array(&self) -> Array2<f32> {
+            images_to_array2(&self.train_data)
+        }
+        /// training images as a contiguous `Array3<f32>` of shape `[n, image_rows, image_cols]`
+        pub fn train_images_array3(&self) -> Array3<f32> {
+            self.train_images_array()
+                .into_shape_with_order((self.train_data.len(), self.image_rows, self.image_cols))
+                .expect("image_rows * image_cols must match the flattened pixel count")
+        }
+        /// training labels, one-hot encoded into an `Array2<f32>` of shape `[n, num_classes]`
+        pub fn train_labels_onehot(&self) -> Array2<f32> {
+            labels_to_onehot(&self.train_labels, self.num_classes)
+        }
+        /// validation images as a contiguous `Array2<f32>` of shape `[n, image_rows * image_cols]`
+        pub fn val_images_array(&self) -> Array2<f32> {
+            images_to_array2(&self.val_data)
+        }
+        /// validation images as a contiguous `Array3<f32>` of shape `[n, image_rows, image_cols]`
+        pub fn val_images_array3(&self) -> Array3<f32> {
+            self.val_images_array()
+                .into_shape_with_order((self.val_data.len(), self.image_rows, self.image_cols))
+                .expect("image_rows * image_cols must match the flattened pixel count")
+        }
+        /// validation labels, one-hot encoded into an `Array2<f32>` of shape `[n, num_classes]`
+        pub fn val_labels_onehot(&self) -> Array2<f32> {
+            labels_to_onehot(&self.val_labels, self.num_classes)
+        }
+        /// test images as a contiguous `Array2<f32>` of shape `[n, image_rows * image_cols]`
+        pub fn test_images_array(&self) -> Array2<f32> {
+            images_to_array2(&self.test_data)
+        }
+        /// test images as a contiguous `Array3<f32>` of shape `[n, image_rows, image_cols]`
+        pub fn test_images_array3(&self) -> Array3<f32> {
+            self.test_images_array()
+                .into_shape_with_order((self.test_data.len(), self.image_rows, self.image_cols))
+                .expect("image_rows * image_cols must match the flattened pixel count")
+        }
+        /// test labels, one-hot encoded into an `Array2<f32>` of shape `[n, num_classes]`
+        pub fn test_labels_onehot(&self) -> Array2<f32> {
+            labels_to_onehot(&self.test_labels, self.num_classes)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    /// gzip `data` to `path`, for feeding synthetic IDX fixtures through `read_gzip`
+    fn write_gz_fixture(path: &str, data: &[u8]) {
+        let file = File::create(path).unwrap();
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_read_mnist_labels_rejects_wrong_magic() {
+        let path = "test_fixture_labels_wrong_magic.gz";
+        let mut data = 0u32.to_be_bytes().to_vec(); // not LABEL_MAGIC
+        data.extend_from_slice(&2u32.to_be_bytes()); // num_labels
+        data.extend_from_slice(&[0u8, 1]);
+        write_gz_fixture(path, &data);
+        let result = read_mnist_labels(path);
+        fs::remove_file(path).unwrap();
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("magic number"));
+    }
+
+    #[test]
+    fn test_read_mnist_labels_rejects_length_mismatch() {
+        let path = "test_fixture_labels_length_mismatch.gz";
+        let mut data = LABEL_MAGIC.to_be_bytes().to_vec();
+        data.extend_from_slice(&10u32.to_be_bytes()); // declares 10 labels
+        data.extend_from_slice(&[0u8, 1, 2]); // but only 3 bytes follow
+        write_gz_fixture(path, &data);
+        let result = read_mnist_labels(path);
+        fs::remove_file(path).unwrap();
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("count mismatch"));
+    }
+
+    #[test]
+    fn test_read_mnist_images_rejects_wrong_magic() {
+        let path = "test_fixture_images_wrong_magic.gz";
+        let mut data = 0u32.to_be_bytes().to_vec(); // not IMAGE_MAGIC
+        data.extend_from_slice(&1u32.to_be_bytes()); // num_images
+        data.extend_from_slice(&2u32.to_be_bytes()); // rows
+        data.extend_from_slice(&2u32.to_be_bytes()); // cols
+        data.extend_from_slice(&[0u8; 4]); // pixel data
+        write_gz_fixture(path, &data);
+        let result = read_mnist_images(path, &PixelTransform::Normalize01);
+        fs::remove_file(path).unwrap();
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("magic number"));
+    }
+
+    #[test]
+    fn test_read_mnist_images_rejects_length_mismatch() {
+        let path = "test_fixture_images_length_mismatch.gz";
+        let mut data = IMAGE_MAGIC.to_be_bytes().to_vec();
+        data.extend_from_slice(&1u32.to_be_bytes()); // num_images
+        data.extend_from_slice(&2u32.to_be_bytes()); // rows
+        data.extend_from_slice(&2u32.to_be_bytes()); // cols
+        data.extend_from_slice(&[0u8; 3]); // header declares 4 pixels, only 3 follow
+        write_gz_fixture(path, &data);
+        let result = read_mnist_images(path, &PixelTransform::Normalize01);
+        fs::remove_file(path).unwrap();
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("length mismatch"));
+    }
 
     #[test]
     fn test_download_files() {
@@ -212,4 +1077,222 @@ mod tests {
         let train_labels = reader.train_labels.clone();
         println!("train_labels: {:?}", train_labels);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_validation_split() {
+        let save_dir = "data";
+        let mut reader = MnistReaderBuilder::new(save_dir)
+            .validation_len(5000)
+            .build();
+        reader.load().unwrap();
+        assert_eq!(reader.val_data.len(), 5000);
+        assert_eq!(reader.val_labels.len(), 5000);
+        assert_eq!(reader.train_data.len(), 55000);
+        assert_eq!(reader.train_labels.len(), 55000);
+    }
+
+    #[test]
+    fn test_validation_len_takes_precedence_regardless_of_call_order() {
+        fn with_synthetic_train_data(mut reader: MnistReader) -> MnistReader {
+            reader.train_data = (0..60000u32).map(|i| vec![i as f32]).collect();
+            reader.train_labels = (0..60000u32).map(|i| (i % 10) as u8).collect();
+            reader.split_validation();
+            reader
+        }
+
+        let fraction_then_len = with_synthetic_train_data(
+            MnistReaderBuilder::new("unused")
+                .validation_fraction(0.1)
+                .validation_len(5000)
+                .build(),
+        );
+        assert_eq!(fraction_then_len.val_data.len(), 5000);
+
+        let len_then_fraction = with_synthetic_train_data(
+            MnistReaderBuilder::new("unused")
+                .validation_len(5000)
+                .validation_fraction(0.1)
+                .build(),
+        );
+        assert_eq!(len_then_fraction.val_data.len(), 5000);
+    }
+
+    #[test]
+    #[should_panic(expected = "batch_size must be greater than 0")]
+    fn test_data_loader_rejects_zero_batch_size() {
+        let data: Vec<Vec<f32>> = vec![vec![0.0]];
+        let labels: Vec<u8> = vec![0];
+        let _ = DataLoader::new(&data, &labels, 0);
+    }
+
+    #[test]
+    fn test_data_loader_batches() {
+        let data: Vec<Vec<f32>> = (0..10).map(|i| vec![i as f32]).collect();
+        let labels: Vec<u8> = (0..10).collect();
+        let loader = DataLoader::new(&data, &labels, 4);
+        let batches: Vec<_> = loader.collect();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].0.len(), 4);
+        assert_eq!(batches[2].0.len(), 2); // final partial batch kept by default
+
+        let loader = DataLoader::new(&data, &labels, 4).drop_last(true);
+        let batches: Vec<_> = loader.collect();
+        assert_eq!(batches.len(), 2);
+        assert!(batches.iter().all(|(images, _)| images.len() == 4));
+    }
+
+    #[test]
+    fn test_data_loader_shuffle_is_a_permutation() {
+        let data: Vec<Vec<f32>> = (0..20).map(|i| vec![i as f32]).collect();
+        let labels: Vec<u8> = (0..20).map(|i| i as u8).collect();
+        let loader = DataLoader::new(&data, &labels, 20).shuffle(true).seed(42);
+        let (images, batch_labels): (Vec<Vec<f32>>, Vec<u8>) = loader.collect::<Vec<_>>().remove(0);
+        let mut seen = batch_labels.clone();
+        seen.sort_unstable();
+        assert_eq!(seen, labels);
+        assert_eq!(images.len(), 20);
+    }
+
+    #[test]
+    fn test_pixel_transform_raw_skips_normalization() {
+        let mut image: Vec<f32> = vec![128.0];
+        apply_pixel_transform(&mut image, &PixelTransform::Raw);
+        assert_eq!(base_pixel_value(128, &PixelTransform::Raw), 128.0);
+        assert_eq!(base_pixel_value(128, &PixelTransform::Normalize01), 128.0 / 255.0);
+    }
+
+    #[test]
+    fn test_pixel_transform_binarize() {
+        let mut image: Vec<f32> = vec![0.2, 0.6, 0.5];
+        apply_pixel_transform(&mut image, &PixelTransform::Binarize { threshold: 0.5 });
+        assert_eq!(image, vec![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_pixel_transform_standardize() {
+        let mut image: Vec<f32> = vec![1.0, 2.0, 3.0];
+        apply_pixel_transform(&mut image, &PixelTransform::Standardize { mean: 2.0, std: 1.0 });
+        assert_eq!(image, vec![-1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_pixel_transform_custom() {
+        let mut image: Vec<f32> = vec![1.0, 2.0, 3.0];
+        let transform = PixelTransform::Custom(Rc::new(|pixels: &mut [f32]| {
+            for p in pixels.iter_mut() {
+                *p *= 2.0;
+            }
+        }));
+        apply_pixel_transform(&mut image, &transform);
+        assert_eq!(image, vec![2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_dataset_source_mapping() {
+        let mnist = Dataset::Mnist.source();
+        assert_eq!(mnist.train_images_file, "train-images-idx3-ubyte.gz");
+        assert_eq!(mnist.train_labels_file, "train-labels-idx1-ubyte.gz");
+        assert_eq!(mnist.test_images_file, "t10k-images-idx3-ubyte.gz");
+        assert_eq!(mnist.test_labels_file, "t10k-labels-idx1-ubyte.gz");
+
+        let fashion = Dataset::FashionMnist.source();
+        assert_eq!(fashion.train_images_file, "train-images-idx3-ubyte.gz");
+        assert!(fashion.mnist_url.contains("fashion-mnist"));
+
+        let kuzushiji = Dataset::KuzushijiMnist.source();
+        assert!(kuzushiji.mnist_url.contains("kmnist"));
+
+        let emnist = Dataset::Emnist.source();
+        assert_eq!(emnist.train_images_file, "emnist-balanced-train-images-idx3-ubyte.gz");
+        assert_eq!(emnist.test_labels_file, "emnist-balanced-test-labels-idx1-ubyte.gz");
+
+        let custom_source = DatasetSource {
+            mnist_url: "https://example.com/data".to_string(),
+            train_images_file: "a.gz".to_string(),
+            train_labels_file: "b.gz".to_string(),
+            test_images_file: "c.gz".to_string(),
+            test_labels_file: "d.gz".to_string(),
+        };
+        let custom = Dataset::Custom(custom_source.clone()).source();
+        assert_eq!(custom.mnist_url, custom_source.mnist_url);
+        assert_eq!(custom.train_images_file, custom_source.train_images_file);
+    }
+
+    #[test]
+    fn test_dataset_num_classes() {
+        assert_eq!(Dataset::Mnist.num_classes(), 10);
+        assert_eq!(Dataset::FashionMnist.num_classes(), 10);
+        assert_eq!(Dataset::KuzushijiMnist.num_classes(), 10);
+        assert_eq!(Dataset::Emnist.num_classes(), 47);
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn test_labels_onehot_uses_configured_num_classes() {
+        let mut reader = MnistReaderBuilder::new("unused")
+            .dataset(Dataset::Emnist)
+            .build();
+        reader.train_data = vec![vec![0.0], vec![0.0]];
+        reader.train_labels = vec![0, 46];
+        let onehot = reader.train_labels_onehot();
+        assert_eq!(onehot[[1, 46]], 1.0);
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    #[should_panic(expected = "out of range")]
+    fn test_labels_onehot_panics_on_out_of_range_label() {
+        let mut reader = MnistReader::new("unused");
+        reader.train_data = vec![vec![0.0]];
+        reader.train_labels = vec![10]; // default num_classes is 10, valid labels are 0..=9
+        let _ = reader.train_labels_onehot();
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn test_train_images_array_shapes() {
+        let mut reader = MnistReader::new("unused");
+        reader.train_data = vec![vec![0.0; 4]; 3]; // 3 images of 2x2 pixels
+        reader.image_rows = 2;
+        reader.image_cols = 2;
+        assert_eq!(reader.train_images_array().shape(), &[3, 4]);
+        assert_eq!(reader.train_images_array3().shape(), &[3, 2, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    #[should_panic(expected = "image rows must all be the same length")]
+    fn test_train_images_array_panics_on_ragged_images() {
+        let mut reader = MnistReader::new("unused");
+        reader.train_data = vec![vec![0.0; 4], vec![0.0; 3]];
+        let _ = reader.train_images_array();
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    #[should_panic(expected = "image_rows * image_cols must match")]
+    fn test_train_images_array3_panics_on_dimension_mismatch() {
+        let mut reader = MnistReader::new("unused");
+        reader.train_data = vec![vec![0.0; 4]; 2];
+        reader.image_rows = 3; // 3 * 3 = 9, doesn't match the 4 flattened pixels above
+        reader.image_cols = 3;
+        let _ = reader.train_images_array3();
+    }
+
+    #[test]
+    fn test_crc32c_known_value() {
+        // "123456789" is the standard CRC32C check string; its checksum is well known.
+        assert_eq!(crc32c(b"123456789"), 0xE3069283);
+    }
+
+    #[test]
+    fn test_tfrecord_entry_round_trip_lengths() {
+        let example = encode_tfrecord_example(&[0.0, 0.5, 1.0], 7);
+        let mut buf = Vec::new();
+        write_tfrecord_entry(&mut buf, &example).unwrap();
+        // 8-byte length + 4-byte crc + data + 4-byte crc
+        assert_eq!(buf.len(), 8 + 4 + example.len() + 4);
+        let length = u64::from_le_bytes(buf[0..8].try_into().unwrap());
+        assert_eq!(length as usize, example.len());
+    }
+}
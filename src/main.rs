@@ -11,7 +11,7 @@ fn main() {
     // print the first image
     let train_data: Vec<Vec<f32>> = mnist.train_data;
     println!("images[0]={:?}", train_data[0]);
-    print_image(&train_data[0]);
+    print_image(&train_data[0], mnist.image_cols);
     // print the first label
     let train_labels: Vec<u8> = mnist.train_labels;
     println!("labels[0]={:?}", train_labels[0]);